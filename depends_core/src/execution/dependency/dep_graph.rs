@@ -0,0 +1,195 @@
+//! Topological ordering and cycle detection over a dependency graph,
+//! independent of `Resolve`/`Visitor` so a graph can be inspected and
+//! validated before it's ever run. A DFS post-order walk lists every node
+//! only after everything it depends on, detecting cycles along the way by
+//! tracking which nodes are still on the current DFS stack.
+
+use std::collections::HashMap;
+
+/// Anything that can describe its own position in a dependency graph: its
+/// own identity, and the identities of the nodes it directly depends on.
+/// `DepGraph` only needs the edges, not the resolved values. Neither
+/// `Dependency` nor `InputNode` implement this directly — a `Dependency<T>`
+/// wraps exactly one inner node, it has no notion of "its own dependencies"
+/// as a set of ids — so a caller introspecting a graph built out of them
+/// mirrors its shape with [`SimpleNode`] (or another small adapter over
+/// their own id type) rather than getting `GraphNode` for free.
+pub trait GraphNode: Clone {
+    type Id: Copy + Eq + std::hash::Hash + std::fmt::Debug;
+
+    fn id(&self) -> Self::Id;
+    fn dependency_ids(&self) -> Vec<Self::Id>;
+}
+
+/// A ready-made [`GraphNode`] for the common case: a plain `(id, dependency
+/// ids)` pair, with no node type of its own to adapt.
+#[derive(Debug, Clone)]
+pub struct SimpleNode<Id> {
+    id: Id,
+    dependency_ids: Vec<Id>,
+}
+
+impl<Id> SimpleNode<Id> {
+    pub fn new(id: Id, dependency_ids: Vec<Id>) -> Self {
+        Self { id, dependency_ids }
+    }
+}
+
+impl<Id> GraphNode for SimpleNode<Id>
+where
+    Id: Copy + Eq + std::hash::Hash + std::fmt::Debug,
+{
+    type Id = Id;
+
+    fn id(&self) -> Self::Id {
+        self.id
+    }
+
+    fn dependency_ids(&self) -> Vec<Self::Id> {
+        self.dependency_ids.clone()
+    }
+}
+
+/// The nodes on a cycle, in the order the DFS encountered them, starting
+/// and ending at the repeated node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleError<Id> {
+    pub cycle: Vec<Id>,
+}
+
+impl<Id: std::fmt::Debug> std::fmt::Display for CycleError<Id> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cycle detected in dependency graph: {:?}", self.cycle)
+    }
+}
+
+impl<Id: std::fmt::Debug> std::error::Error for CycleError<Id> {}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    /// On the current DFS stack: reaching it again means a cycle.
+    Gray,
+    /// Fully visited: safe to skip if reached again.
+    Black,
+}
+
+/// A view over a dependency graph rooted at a set of nodes, queried by id
+/// via a `lookup` function rather than owning the nodes directly, so it
+/// works the same whether the caller's nodes live in a `Vec`, a `HashMap`,
+/// or are resolved lazily.
+pub struct DepGraph<N: GraphNode> {
+    roots: Vec<N>,
+}
+
+impl<N: GraphNode> DepGraph<N> {
+    pub fn new(roots: Vec<N>) -> Self {
+        Self { roots }
+    }
+
+    /// Nodes in an order where every node appears only after all of its
+    /// dependencies, computed via a DFS post-order over `dependency_ids`.
+    pub fn resolution_order(
+        &self,
+        lookup: impl Fn(N::Id) -> Option<N>,
+    ) -> Result<impl Iterator<Item = N::Id>, CycleError<N::Id>> {
+        let mut marks = HashMap::new();
+        let mut stack_path = Vec::new();
+        let mut order = Vec::new();
+        for root in &self.roots {
+            visit(root, &lookup, &mut marks, &mut stack_path, &mut order)?;
+        }
+        Ok(order.into_iter())
+    }
+
+    /// Validates that the graph reachable from the roots has no cycles, for
+    /// callers that only want a yes/no answer before resolving. Runs the
+    /// same walk as [`DepGraph::resolution_order`] and discards the order
+    /// it collects.
+    pub fn try_validate(&self, lookup: impl Fn(N::Id) -> Option<N>) -> Result<(), CycleError<N::Id>> {
+        self.resolution_order(lookup).map(|_| ())
+    }
+}
+
+fn visit<N: GraphNode>(
+    node: &N,
+    lookup: &impl Fn(N::Id) -> Option<N>,
+    marks: &mut HashMap<N::Id, Mark>,
+    stack_path: &mut Vec<N::Id>,
+    order: &mut Vec<N::Id>,
+) -> Result<(), CycleError<N::Id>> {
+    let id = node.id();
+    match marks.get(&id) {
+        Some(Mark::Black) => return Ok(()),
+        Some(Mark::Gray) => {
+            let start = stack_path.iter().position(|n| *n == id).unwrap_or(0);
+            let mut cycle = stack_path[start..].to_vec();
+            cycle.push(id);
+            return Err(CycleError { cycle });
+        }
+        None => {}
+    }
+
+    marks.insert(id, Mark::Gray);
+    stack_path.push(id);
+    for dep_id in node.dependency_ids() {
+        if let Some(dep) = lookup(dep_id) {
+            visit(&dep, lookup, marks, stack_path, order)?;
+        }
+    }
+    stack_path.pop();
+
+    marks.insert(id, Mark::Black);
+    order.push(id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn graph(edges: &[(u32, &[u32])]) -> (Vec<SimpleNode<u32>>, HashMap<u32, SimpleNode<u32>>) {
+        let nodes: Vec<_> = edges
+            .iter()
+            .map(|(id, deps)| SimpleNode::new(*id, deps.to_vec()))
+            .collect();
+        let by_id = nodes.iter().map(|n| (n.id, n.clone())).collect();
+        (nodes, by_id)
+    }
+
+    #[test]
+    fn resolution_order_puts_every_node_after_its_dependencies() {
+        // 0 depends on 1 and 2; 2 depends on 1.
+        let (nodes, by_id) = graph(&[(0, &[1, 2]), (1, &[]), (2, &[1])]);
+        let order: Vec<_> = DepGraph::new(nodes)
+            .resolution_order(|id| by_id.get(&id).cloned())
+            .unwrap()
+            .collect();
+
+        let pos = |id: u32| order.iter().position(|&n| n == id).unwrap();
+        assert!(pos(1) < pos(2));
+        assert!(pos(2) < pos(0));
+    }
+
+    #[test]
+    fn try_validate_detects_a_cycle() {
+        // 0 -> 1 -> 2 -> 0
+        let (nodes, by_id) = graph(&[(0, &[1]), (1, &[2]), (2, &[0])]);
+        let err = DepGraph::new(nodes)
+            .try_validate(|id| by_id.get(&id).cloned())
+            .unwrap_err();
+
+        assert!(err.cycle.contains(&0));
+        assert!(err.cycle.contains(&1));
+        assert!(err.cycle.contains(&2));
+    }
+
+    #[test]
+    fn try_validate_accepts_an_acyclic_graph() {
+        let (nodes, by_id) = graph(&[(0, &[1]), (1, &[])]);
+        assert!(DepGraph::new(nodes)
+            .try_validate(|id| by_id.get(&id).cloned())
+            .is_ok());
+    }
+}
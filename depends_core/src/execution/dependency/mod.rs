@@ -1,16 +1,24 @@
+mod dep_graph;
 mod dep_ref;
 mod dep_state;
+mod persist;
+mod push;
+mod sync_dep;
 
 use std::{
     cell::{Ref, RefCell},
     rc::Rc,
 };
 
+pub use dep_graph::{CycleError, DepGraph, GraphNode, SimpleNode};
 pub use dep_ref::DepRef;
 pub use dep_state::DependencyState;
+pub use persist::{GraphCache, GraphEncoder, StableNodeId};
+pub use push::{PushResolver, RevDepGraph};
+pub use sync_dep::SyncDependency;
 
 use super::{HashValue, NodeHash, Resolve};
-use crate::execution::{error::ResolveResult, NodeState, Visitor};
+use crate::execution::{error::ResolveResult, hash_value::Fingerprint, NodeState, Visitor};
 
 /// Short-hand for a reference to a single dependency.
 pub type SingleRef<'a, T> = DepRef<'a, Ref<'a, NodeState<T>>>;
@@ -38,6 +46,49 @@ where
             dependency,
         }
     }
+
+    /// Builds a dependency whose `last_state` is pre-seeded from a
+    /// [`GraphCache`] loaded at startup, keyed by `id`. If the graph's
+    /// shape changed since the cache was written, `id` simply won't be
+    /// present and this behaves exactly like [`Dependency::new`]: there is
+    /// no stale-match case to guard against because a structural or type
+    /// change always produces a different `StableNodeId`.
+    pub fn with_cache(dependency: T, id: &StableNodeId, cache: &GraphCache) -> Self {
+        Self {
+            last_state: RefCell::new(cache.get(id)),
+            dependency,
+        }
+    }
+
+    /// The hash this dependency observed on its most recent resolve, if
+    /// any. Used by [`GraphEncoder`] to persist state across process
+    /// restarts without a second walk of the graph.
+    pub fn observed_hash(&self) -> Option<NodeHash> {
+        *self.last_state.borrow()
+    }
+}
+
+impl<T> Dependency<T>
+where
+    T: Resolve,
+    for<'a> <T as Resolve>::Output<'a>: HashValue,
+{
+    /// Resolves this dependency and records the hash it observed into
+    /// `encoder`, keyed by `id`, in the same call. Use this in place of
+    /// [`Resolve::resolve`]/`resolve_root` when driving a pass that should
+    /// also persist a [`GraphCache`]: encoding then piggybacks on the
+    /// resolve itself rather than requiring a second, separate walk of the
+    /// graph afterwards to re-read what each node observed.
+    pub fn resolve_recording(
+        &self,
+        id: &StableNodeId,
+        visitor: &mut impl Visitor,
+        encoder: &mut GraphEncoder,
+    ) -> ResolveResult<<Self as Resolve>::Output<'_>> {
+        let output = self.resolve(visitor)?;
+        encoder.record(id.clone(), self);
+        Ok(output)
+    }
 }
 
 impl<T> Resolve for Dependency<T>
@@ -88,9 +139,20 @@ mod tests {
     }
 
     impl HashValue for Foo {
+        // TEST FIXTURE ONLY, not a template for a real `HashValue` impl.
+        // `Fingerprint` is meant to combine two *independently-seeded*
+        // hashers; `Visitor::hasher()` only ever hands back one `Hasher`
+        // instance, so `lane_1` below is derived from `lane_0`'s own
+        // output rather than an independent draw. That's weaker than the
+        // two-independent-lanes design `Fingerprint` assumes, it's only
+        // good enough for this crate's own "did the state change" tests.
         fn hash_value(&self, hasher: &mut impl Hasher) -> NodeHash {
             hasher.write_u8(self.0);
-            NodeHash::Hashed(hasher.finish())
+            let lane_0 = hasher.finish();
+            hasher.write_u64(lane_0);
+            hasher.write_u8(!self.0);
+            let lane_1 = hasher.finish();
+            NodeHash::Hashed(Fingerprint::new(lane_0, lane_1))
         }
     }
 
@@ -130,4 +192,55 @@ mod tests {
             assert!(output.is_dirty());
         }
     }
+
+    #[test]
+    #[serial]
+    fn resolve_recording_persists_the_observed_hash_into_an_encoder() {
+        reset_node_id();
+        let node = InputNode::new(Foo(57));
+        let dependency = Dependency::new(Rc::clone(&node));
+        let mut visitor = HashSetVisitor::new();
+        let mut encoder = GraphEncoder::new();
+        let id = StableNodeId::new("Foo", &[0]);
+
+        dependency
+            .resolve_recording(&id, &mut visitor, &mut encoder)
+            .unwrap();
+
+        let cache = encoder.into_cache();
+        assert_eq!(cache.get(&id), dependency.observed_hash());
+        assert!(cache.get(&id).is_some());
+    }
+
+    #[test]
+    #[serial]
+    fn with_cache_reports_clean_on_a_seeded_match_and_dirty_on_a_miss() {
+        reset_node_id();
+        let node = InputNode::new(Foo(57));
+        let mut visitor = HashSetVisitor::new();
+
+        // Capture the hash a fresh resolve observes, as if a previous
+        // process had persisted it.
+        let warm_up = Dependency::new(Rc::clone(&node));
+        warm_up.resolve_root(&mut visitor).unwrap();
+        let hash = warm_up.observed_hash().unwrap();
+
+        let id = StableNodeId::new("Foo", &[0]);
+        let mut cache = GraphCache::default();
+        cache.record(id.clone(), hash);
+
+        // Seeded with a matching persisted hash: the very first resolve in
+        // this "new process" reports Clean, without ever having resolved
+        // before.
+        let seeded = Dependency::with_cache(Rc::clone(&node), &id, &cache);
+        let output = seeded.resolve_root(&mut visitor).unwrap();
+        assert!(!output.is_dirty());
+
+        // A miss (no entry for this id) behaves exactly like
+        // `Dependency::new`: Dirty on the first resolve.
+        let miss_id = StableNodeId::new("Foo", &[1]);
+        let unseeded = Dependency::with_cache(Rc::clone(&node), &miss_id, &cache);
+        let output = unseeded.resolve_root(&mut visitor).unwrap();
+        assert!(output.is_dirty());
+    }
 }
@@ -0,0 +1,190 @@
+//! An on-disk cache of the hashes [`Dependency`] observes, so a [`Dependency`]
+//! can be seeded with [`Dependency::with_cache`] and report `Clean` without
+//! recomputing anything the first time it resolves in a new process. Keyed
+//! by [`StableNodeId`] rather than the volatile `reset_node_id` counter, so
+//! a structural or type change between runs is a cache miss, not a stale
+//! match.
+
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+};
+
+use super::Dependency;
+use crate::execution::{hash_value::Fingerprint, NodeHash, Resolve};
+
+/// A stable identity for a node within the graph, used as the persisted
+/// cache key in place of the volatile `reset_node_id` counter.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StableNodeId(String);
+
+impl StableNodeId {
+    /// Builds an id from a node's type name (via `Named::name()`) and its
+    /// path of child indices from the root. Two nodes only collide here if
+    /// they have the same type sitting at the same position in the graph.
+    ///
+    /// Each segment is length-prefixed rather than joined with a bare `/`,
+    /// since `Named::name()` is an arbitrary user-supplied string: a naive
+    /// `"{type_name}/{path}"` join would let `("Foo", &[0])` and
+    /// `("Foo/0", &[])` collide on the identical string `"Foo/0"`. Prefixing
+    /// each segment with its own byte length makes the boundary unambiguous
+    /// regardless of what characters a segment contains.
+    pub fn new(type_name: &'static str, path: &[usize]) -> Self {
+        let mut id = String::new();
+        push_segment(&mut id, type_name);
+        for segment in path {
+            push_segment(&mut id, &segment.to_string());
+        }
+        StableNodeId(id)
+    }
+}
+
+fn push_segment(id: &mut String, segment: &str) {
+    id.push_str(&segment.len().to_string());
+    id.push(':');
+    id.push_str(segment);
+}
+
+/// A map from a node's [`StableNodeId`] to the [`NodeHash`] observed for it
+/// the last time the graph was resolved.
+#[derive(Debug, Default, Clone)]
+pub struct GraphCache {
+    entries: HashMap<StableNodeId, NodeHash>,
+}
+
+impl GraphCache {
+    pub fn get(&self, id: &StableNodeId) -> Option<NodeHash> {
+        self.entries.get(id).copied()
+    }
+
+    pub fn record(&mut self, id: StableNodeId, hash: NodeHash) {
+        self.entries.insert(id, hash);
+    }
+
+    /// Writes the cache as a sequence of `(id_len: u32, id: bytes, fingerprint: u128)`
+    /// records. Deliberately simple: this is a cache, not a format other
+    /// tools need to read, so there's no need for a general serialization
+    /// framework here.
+    pub fn serialize(&self, writer: &mut impl Write) -> io::Result<()> {
+        for (id, hash) in &self.entries {
+            let NodeHash::Hashed(fingerprint) = hash else {
+                continue;
+            };
+            let bytes = id.0.as_bytes();
+            writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+            writer.write_all(bytes)?;
+            writer.write_all(&fingerprint.as_u128().to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn deserialize(reader: &mut impl Read) -> io::Result<Self> {
+        let mut entries = HashMap::new();
+        loop {
+            let mut len_buf = [0u8; 4];
+            match reader.read_exact(&mut len_buf) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+            let len = u32::from_le_bytes(len_buf) as usize;
+            let mut id_buf = vec![0u8; len];
+            reader.read_exact(&mut id_buf)?;
+            let mut hash_buf = [0u8; 16];
+            reader.read_exact(&mut hash_buf)?;
+            let id = StableNodeId(String::from_utf8_lossy(&id_buf).into_owned());
+            let raw = u128::from_le_bytes(hash_buf);
+            let fingerprint = Fingerprint::new((raw >> 64) as u64, raw as u64);
+            entries.insert(id, NodeHash::Hashed(fingerprint));
+        }
+        Ok(Self { entries })
+    }
+}
+
+/// Accumulates `(StableNodeId, NodeHash)` entries by reading the hash each
+/// [`Dependency`] observed on its last resolve. [`Dependency::resolve_recording`]
+/// is the intended entry point: it resolves the dependency and records its
+/// hash into a `GraphEncoder` in the same call, so encoding happens
+/// alongside a normal `resolve_root` pass instead of a second walk of the
+/// graph afterwards. [`record`] is also available directly, for callers
+/// that already have a `StableNodeId` and an already-resolved `Dependency`
+/// in hand.
+///
+/// [`record`]: GraphEncoder::record
+#[derive(Debug, Default)]
+pub struct GraphEncoder {
+    cache: GraphCache,
+}
+
+impl GraphEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the hash `dependency` observed on its most recent resolve,
+    /// if it has resolved at least once. A dependency that was never
+    /// reached this pass (e.g. short-circuited by a `Clean` ancestor)
+    /// simply keeps whatever entry, if any, is already in the cache.
+    pub fn record<T>(&mut self, id: StableNodeId, dependency: &Dependency<T>)
+    where
+        T: Resolve,
+    {
+        if let Some(hash) = dependency.observed_hash() {
+            self.cache.record(id, hash);
+        }
+    }
+
+    pub fn into_cache(self) -> GraphCache {
+        self.cache
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialize_then_deserialize_round_trips_entries() {
+        let mut cache = GraphCache::default();
+        cache.record(
+            StableNodeId::new("Foo", &[0]),
+            NodeHash::Hashed(Fingerprint::new(1, 2)),
+        );
+        cache.record(StableNodeId::new("Bar", &[0, 1]), NodeHash::NotHashed);
+
+        let mut bytes = Vec::new();
+        cache.serialize(&mut bytes).unwrap();
+        let loaded = GraphCache::deserialize(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(
+            loaded.get(&StableNodeId::new("Foo", &[0])),
+            Some(NodeHash::Hashed(Fingerprint::new(1, 2)))
+        );
+        // `NotHashed` entries aren't written out, there's nothing to cache.
+        assert_eq!(loaded.get(&StableNodeId::new("Bar", &[0, 1])), None);
+    }
+
+    #[test]
+    fn structural_change_is_a_cache_miss_not_a_stale_match() {
+        let mut cache = GraphCache::default();
+        cache.record(
+            StableNodeId::new("Foo", &[0]),
+            NodeHash::Hashed(Fingerprint::new(1, 2)),
+        );
+
+        // Same position, different type, and same type, different
+        // position: neither should find the `Foo`-at-`[0]` entry.
+        assert_eq!(cache.get(&StableNodeId::new("Baz", &[0])), None);
+        assert_eq!(cache.get(&StableNodeId::new("Foo", &[1])), None);
+    }
+
+    #[test]
+    fn differently_shaped_paths_do_not_collide() {
+        // A naive `"{type_name}/{path}"` join would make these the same
+        // string ("Foo/0"); length-prefixing must keep them distinct.
+        assert_ne!(
+            StableNodeId::new("Foo", &[0]),
+            StableNodeId::new("Foo/0", &[])
+        );
+    }
+}
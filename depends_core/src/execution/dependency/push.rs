@@ -0,0 +1,295 @@
+//! Push-based invalidation, opt-in alongside the pull-based [`Dependency`].
+//! A [`RevDepGraph`] records, for each node, which dependents observe it;
+//! [`PushResolver::notify_updated`] walks those reverse edges to mark the
+//! transitive set of dependents dirty as soon as an input changes, so a
+//! later resolve only needs to recompute that dirty frontier instead of
+//! re-hashing every dependency on every pass. [`PushInputNode`] calls
+//! `notify_updated` as part of its own `update`, and [`PushDependency`]
+//! consults `is_dirty` as part of its own `resolve`, so the two actually
+//! sit on a real update/resolve path instead of being a bystander the
+//! caller has to remember to wire up by hand.
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use super::{DepRef, DependencyState};
+use crate::execution::{error::ResolveResult, HashValue, InputNode, NodeHash, Resolve, UpdateInput, Visitor};
+
+/// For each node, the set of dependents that observe it — the reverse of
+/// the usual dependency edges ("if I change, who needs to know").
+#[derive(Debug, Default)]
+pub struct RevDepGraph<Id> {
+    dependents: HashMap<Id, Vec<Id>>,
+}
+
+impl<Id> RevDepGraph<Id>
+where
+    Id: Copy + Eq + std::hash::Hash,
+{
+    pub fn new() -> Self {
+        Self {
+            dependents: HashMap::new(),
+        }
+    }
+
+    /// Records that `dependent` observes `dependency`, i.e. adds the
+    /// reverse edge `dependency -> dependent`.
+    pub fn add_edge(&mut self, dependency: Id, dependent: Id) {
+        self.dependents.entry(dependency).or_default().push(dependent);
+    }
+
+    /// The transitive set of dependents reachable from `changed` by
+    /// walking reverse edges: dependents of dependents of ... of each
+    /// changed node, retaining only nodes actually reachable this way.
+    pub fn transitive_dependents(&self, changed: &[Id]) -> HashSet<Id> {
+        let mut dirty = HashSet::new();
+        let mut stack: Vec<Id> = changed.to_vec();
+        while let Some(id) = stack.pop() {
+            if let Some(dependents) = self.dependents.get(&id) {
+                for &dependent in dependents {
+                    if dirty.insert(dependent) {
+                        stack.push(dependent);
+                    }
+                }
+            }
+        }
+        dirty
+    }
+}
+
+/// An opt-in resolver that tracks a running dirty frontier instead of
+/// re-hashing the whole graph on every pass. Built from a [`RevDepGraph`]
+/// describing which nodes observe which.
+#[derive(Debug, Default)]
+pub struct PushResolver<Id> {
+    rev_dep_graph: RevDepGraph<Id>,
+    dirty: HashSet<Id>,
+}
+
+impl<Id> PushResolver<Id>
+where
+    Id: Copy + Eq + std::hash::Hash,
+{
+    pub fn new(rev_dep_graph: RevDepGraph<Id>) -> Self {
+        Self {
+            rev_dep_graph,
+            dirty: HashSet::new(),
+        }
+    }
+
+    /// Call when an `InputNode::update` mutates `changed_input`. Marks the
+    /// transitive set of dependents dirty by walking reverse edges, so a
+    /// later resolve only needs to recompute nodes in the resulting
+    /// frontier.
+    pub fn notify_updated(&mut self, changed_input: Id) {
+        self.dirty.insert(changed_input);
+        self.dirty
+            .extend(self.rev_dep_graph.transitive_dependents(&[changed_input]));
+    }
+
+    /// Whether `id` is in the current dirty frontier, i.e. needs
+    /// recomputing before its value can be trusted.
+    pub fn is_dirty(&self, id: Id) -> bool {
+        self.dirty.contains(&id)
+    }
+
+    /// Clears the dirty frontier after a resolution pass has recomputed
+    /// everything in it, mirroring `Clean::clean` on a single node but for
+    /// the whole frontier at once.
+    pub fn clear(&mut self) {
+        self.dirty.clear();
+    }
+
+    /// Clears a single node out of the dirty frontier once it alone has
+    /// been recomputed, for callers (like [`PushDependency`]) that resolve
+    /// one node at a time rather than an entire frontier together.
+    pub fn clear_one(&mut self, id: Id) {
+        self.dirty.remove(&id);
+    }
+}
+
+/// Wraps an [`InputNode`], forwarding `update` and then eagerly notifying
+/// a shared [`PushResolver`] — the actual "when an `InputNode::update`
+/// mutates an input, eagerly mark its dependents dirty" hook, rather than
+/// requiring every caller to remember to call [`PushResolver::notify_updated`]
+/// alongside a plain `InputNode::update`.
+pub struct PushInputNode<T, Id> {
+    inner: Rc<InputNode<T>>,
+    id: Id,
+    resolver: Rc<RefCell<PushResolver<Id>>>,
+}
+
+impl<T, Id> PushInputNode<T, Id>
+where
+    T: UpdateInput,
+    Id: Copy + Eq + std::hash::Hash,
+{
+    pub fn new(inner: Rc<InputNode<T>>, id: Id, resolver: Rc<RefCell<PushResolver<Id>>>) -> Self {
+        Self {
+            inner,
+            id,
+            resolver,
+        }
+    }
+
+    pub fn update(&self, update: T::Update) -> ResolveResult<()> {
+        self.inner.update(update)?;
+        self.resolver.borrow_mut().notify_updated(self.id);
+        Ok(())
+    }
+}
+
+/// A pull-based dependency that consults a shared [`PushResolver`] instead
+/// of re-hashing on every resolve: if the resolver says `id` has no dirty
+/// ancestor (and this is not the first resolve), hashing is skipped
+/// entirely and the node reports `Clean` on the resolver's say-so. This is
+/// the other half of the wiring [`PushInputNode`] starts — the resolve path
+/// that actually reads the dirty frontier `notify_updated` produces,
+/// instead of `PushResolver` being a disconnected dirty-tracker nothing
+/// consults.
+pub struct PushDependency<T, Id> {
+    last_state: RefCell<Option<NodeHash>>,
+    id: Id,
+    resolver: Rc<RefCell<PushResolver<Id>>>,
+    dependency: T,
+}
+
+impl<T, Id> PushDependency<T, Id>
+where
+    T: Resolve,
+    Id: Copy + Eq + std::hash::Hash,
+{
+    pub fn new(dependency: T, id: Id, resolver: Rc<RefCell<PushResolver<Id>>>) -> Self {
+        Self {
+            last_state: RefCell::new(None),
+            id,
+            resolver,
+            dependency,
+        }
+    }
+}
+
+impl<T, Id> Resolve for PushDependency<T, Id>
+where
+    T: Resolve,
+    Id: Copy + Eq + std::hash::Hash,
+    for<'a> <T as Resolve>::Output<'a>: HashValue,
+{
+    type Output<'a>
+        = DepRef<'a, T::Output<'a>>
+    where
+        Self: 'a;
+
+    fn resolve(&self, visitor: &mut impl Visitor) -> ResolveResult<Self::Output<'_>> {
+        let mut last_state = self.last_state.try_borrow_mut()?;
+        let data = self.dependency.resolve(visitor)?;
+
+        let already_resolved = last_state.is_some();
+        let flagged_dirty = self.resolver.borrow().is_dirty(self.id);
+        if already_resolved && !flagged_dirty {
+            // No dirty ancestor since the last resolve: trust the push
+            // signal instead of re-deriving it by hashing and comparing.
+            return Ok(DepRef::new(DependencyState::Clean, data));
+        }
+
+        let current_state = data.hash_value(&mut visitor.hasher());
+        *last_state = Some(current_state);
+        self.resolver.borrow_mut().clear_one(self.id);
+        Ok(DepRef::new(DependencyState::Dirty, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::Hasher;
+
+    use serial_test::serial;
+
+    use super::*;
+    use crate::execution::{
+        hash_value::Fingerprint, identifiable::reset_node_id, Clean, HashSetVisitor, Named,
+    };
+
+    #[derive(Debug, Hash, PartialEq, Eq)]
+    struct Leaf(u8);
+
+    impl Named for Leaf {
+        fn name() -> &'static str {
+            "Leaf"
+        }
+    }
+
+    impl Clean for Leaf {
+        fn clean(&mut self) {}
+    }
+
+    impl HashValue for Leaf {
+        // TEST FIXTURE ONLY, not a template for a real `HashValue` impl —
+        // see the matching comment in `dependency/mod.rs`'s `Foo` impl.
+        fn hash_value(&self, hasher: &mut impl Hasher) -> NodeHash {
+            hasher.write_u8(self.0);
+            let lane_0 = hasher.finish();
+            hasher.write_u64(lane_0);
+            hasher.write_u8(!self.0);
+            let lane_1 = hasher.finish();
+            NodeHash::Hashed(Fingerprint::new(lane_0, lane_1))
+        }
+    }
+
+    impl UpdateInput for Leaf {
+        type Update = u8;
+
+        fn update_mut(&mut self, update: Self::Update) {
+            self.0 = update;
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn push_dependency_skips_hashing_until_notified_of_an_update() {
+        reset_node_id();
+        let node = InputNode::new(Leaf(1));
+        let resolver = Rc::new(RefCell::new(PushResolver::new(RevDepGraph::new())));
+        let input = PushInputNode::new(Rc::clone(&node), "leaf", Rc::clone(&resolver));
+        let dependency = PushDependency::new(Rc::clone(&node), "leaf", Rc::clone(&resolver));
+        let mut visitor = HashSetVisitor::new();
+
+        // Never resolved before: dirty regardless of the push signal.
+        let output = dependency.resolve(&mut visitor).unwrap();
+        assert!(output.is_dirty());
+        drop(output);
+
+        // Resolved once, no update since: Clean without hashing again.
+        let output = dependency.resolve(&mut visitor).unwrap();
+        assert!(!output.is_dirty());
+        drop(output);
+
+        // `PushInputNode::update` notifies the resolver, so the next
+        // resolve sees the dirty signal and recomputes.
+        input.update(2).unwrap();
+        let output = dependency.resolve(&mut visitor).unwrap();
+        assert!(output.is_dirty());
+    }
+
+    #[test]
+    fn notify_updated_marks_transitive_dependents_dirty() {
+        // input -> derived -> root, plus an unrelated sibling.
+        let mut rev_dep_graph = RevDepGraph::new();
+        rev_dep_graph.add_edge("input", "derived");
+        rev_dep_graph.add_edge("derived", "root");
+
+        let mut resolver = PushResolver::new(rev_dep_graph);
+        resolver.notify_updated("input");
+
+        assert!(resolver.is_dirty("input"));
+        assert!(resolver.is_dirty("derived"));
+        assert!(resolver.is_dirty("root"));
+        assert!(!resolver.is_dirty("sibling"));
+
+        resolver.clear();
+        assert!(!resolver.is_dirty("derived"));
+    }
+}
@@ -0,0 +1,403 @@
+//! A `Sync` counterpart to [`Dependency`], plus a minimal thread pool to
+//! drive it, for parallel graph resolution. [`SyncDependency`] swaps
+//! `Rc`/`RefCell` for `Arc`/`Mutex`+`Condvar`: a node shared by more than
+//! one dependent (a diamond) has its hash and `Clean`/`Dirty` decision
+//! computed by exactly one thread per pass, while every other thread
+//! blocks on a `Condvar` instead of racing it. [`ParallelResolver`]
+//! dispatches independent jobs across a small worker pool so unrelated
+//! subgraphs actually run concurrently; [`ParallelResolver::run_graph`]
+//! tracks per-node readiness itself (ready = every dependency already
+//! finished) over a [`GraphNode`](super::GraphNode) graph, rather than
+//! requiring the caller to pre-partition work into waves.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Condvar, Mutex, RwLock},
+};
+
+use super::{dep_graph::GraphNode, DepRef, DependencyState};
+use crate::execution::{error::ResolveResult, HashValue, NodeHash, Resolve, Visitor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PassState {
+    NotStarted,
+    InProgress,
+    Done(DependencyState),
+}
+
+/// A `Sync` counterpart to [`Dependency`]. See the module docs for the
+/// exactly-once-per-pass guarantee this provides for diamond dependencies.
+#[derive(Debug)]
+pub struct SyncDependency<T> {
+    last_state: RwLock<Option<NodeHash>>,
+    pass: Mutex<PassState>,
+    pass_done: Condvar,
+    dependency: Arc<T>,
+}
+
+impl<T> SyncDependency<T>
+where
+    T: Resolve,
+{
+    pub fn new(dependency: Arc<T>) -> Self {
+        Self {
+            last_state: RwLock::new(None),
+            pass: Mutex::new(PassState::NotStarted),
+            pass_done: Condvar::new(),
+            dependency,
+        }
+    }
+
+    /// Resets this node's per-pass coordination so the next `resolve` call
+    /// races fresh instead of immediately observing last pass's `Done`. A
+    /// resolver driving a whole graph calls this on every node before
+    /// starting a new pass.
+    pub fn begin_pass(&self) {
+        *self.pass.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = PassState::NotStarted;
+    }
+}
+
+impl<T> Resolve for SyncDependency<T>
+where
+    T: Resolve + Send + Sync,
+    for<'a> <T as Resolve>::Output<'a>: HashValue,
+{
+    type Output<'a>
+        = DepRef<'a, T::Output<'a>>
+    where
+        Self: 'a;
+
+    fn resolve(&self, visitor: &mut impl Visitor) -> ResolveResult<Self::Output<'_>> {
+        // Exactly one thread per pass claims `NotStarted` and becomes the
+        // claimer; everyone else blocks on the condvar until that thread
+        // publishes `Done`, instead of each computing and comparing its
+        // own hash.
+        let is_claimer = {
+            let mut pass = self.pass.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            loop {
+                match *pass {
+                    PassState::NotStarted => {
+                        *pass = PassState::InProgress;
+                        break true;
+                    }
+                    PassState::InProgress => {
+                        pass = self
+                            .pass_done
+                            .wait(pass)
+                            .unwrap_or_else(|poisoned| poisoned.into_inner());
+                    }
+                    PassState::Done(_) => break false,
+                }
+            }
+        };
+
+        // Every thread still fetches its own `Output` handle - cheap by
+        // this crate's existing convention, since `Dependency::resolve`
+        // (the single-threaded version) already calls the inner
+        // `resolve` unconditionally on every pass regardless of
+        // `Clean`/`Dirty`. What's guarded above is the part that isn't
+        // safe to race: computing the hash and deciding the state.
+        let data = self.dependency.resolve(visitor)?;
+
+        let dep_state = if is_claimer {
+            let current_state = data.hash_value(&mut visitor.hasher());
+            let mut last_state = self
+                .last_state
+                .write()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let state = if last_state.map(|s| s == current_state).unwrap_or(false) {
+                DependencyState::Clean
+            } else {
+                *last_state = Some(current_state);
+                DependencyState::Dirty
+            };
+            drop(last_state);
+
+            *self.pass.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = PassState::Done(state);
+            self.pass_done.notify_all();
+            state
+        } else {
+            match *self.pass.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) {
+                PassState::Done(state) => state,
+                _ => unreachable!("non-claimers only stop waiting once `Done` is set"),
+            }
+        };
+
+        Ok(DepRef::new(dep_state, data))
+    }
+}
+
+/// Dispatches independent jobs across a fixed-size worker pool: callers
+/// queue up one job per node that's ready to resolve (all its dependencies
+/// already resolved), and workers take jobs from the shared queue instead
+/// of one thread working through the graph serially. All workers share a
+/// single queue behind one `Mutex`; the queue is not sharded. A diamond shared between two
+/// jobs still only resolves once: that's `SyncDependency::resolve`'s job,
+/// not the pool's.
+pub struct ParallelResolver {
+    workers: usize,
+}
+
+impl ParallelResolver {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            workers: workers.max(1),
+        }
+    }
+
+    /// Runs every job in `jobs`, stealing from a shared queue across
+    /// `self.workers` threads. Blocks until all jobs complete. The caller
+    /// is responsible for readiness: every job in `jobs` must already have
+    /// all of its dependencies resolved. [`ParallelResolver::run_graph`]
+    /// handles that bookkeeping automatically.
+    pub fn run<F>(&self, jobs: Vec<F>)
+    where
+        F: FnOnce() + Send,
+    {
+        let queue = Mutex::new(jobs.into_iter().collect::<VecDeque<_>>());
+        std::thread::scope(|scope| {
+            for _ in 0..self.workers {
+                scope.spawn(|| loop {
+                    let job = queue
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .pop_front();
+                    match job {
+                        Some(job) => job(),
+                        None => break,
+                    }
+                });
+            }
+        });
+    }
+
+    /// Resolves a graph of [`GraphNode`]s, dispatching each node across the
+    /// pool as soon as every dependency it lists has finished — unlike
+    /// [`run`](ParallelResolver::run), the caller doesn't pre-partition work
+    /// into waves; readiness is tracked here via a per-node count of
+    /// not-yet-finished dependencies, decremented as each dependency
+    /// finishes, with newly-ready nodes pushed onto a shared queue that
+    /// idle workers wait on.
+    pub fn run_graph<N, F>(&self, nodes: &[N], resolve_one: F)
+    where
+        N: GraphNode,
+        N::Id: Send,
+        F: Fn(N::Id) + Send + Sync,
+    {
+        let mut remaining = HashMap::new();
+        let mut dependents: HashMap<N::Id, Vec<N::Id>> = HashMap::new();
+        let mut ready = VecDeque::new();
+
+        for node in nodes {
+            let deps = node.dependency_ids();
+            remaining.insert(node.id(), deps.len());
+            if deps.is_empty() {
+                ready.push_back(node.id());
+            }
+            for dep_id in deps {
+                dependents.entry(dep_id).or_default().push(node.id());
+            }
+        }
+
+        let total = nodes.len();
+        let finished = Mutex::new(0usize);
+        let state = Mutex::new((remaining, ready));
+        let woken = Condvar::new();
+
+        std::thread::scope(|scope| {
+            for _ in 0..self.workers {
+                scope.spawn(|| loop {
+                    let id = {
+                        let mut guard = state.lock().unwrap_or_else(|p| p.into_inner());
+                        loop {
+                            if let Some(id) = guard.1.pop_front() {
+                                break Some(id);
+                            }
+                            if *finished.lock().unwrap_or_else(|p| p.into_inner()) == total {
+                                break None;
+                            }
+                            guard = woken.wait(guard).unwrap_or_else(|p| p.into_inner());
+                        }
+                    };
+                    let Some(id) = id else { break };
+
+                    resolve_one(id);
+
+                    let mut guard = state.lock().unwrap_or_else(|p| p.into_inner());
+                    if let Some(waiting) = dependents.get(&id) {
+                        for &dependent in waiting {
+                            if let Some(count) = guard.0.get_mut(&dependent) {
+                                *count -= 1;
+                                if *count == 0 {
+                                    guard.1.push_back(dependent);
+                                }
+                            }
+                        }
+                    }
+                    *finished.lock().unwrap_or_else(|p| p.into_inner()) += 1;
+                    drop(guard);
+                    woken.notify_all();
+                });
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        hash::Hasher,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+    use crate::execution::{hash_value::Fingerprint, HashSetVisitor};
+
+    /// A leaf whose `hash_value` counts how many times it actually ran the
+    /// hash computation, so concurrent-resolve tests can assert that a
+    /// shared ancestor was only hashed once per pass.
+    #[derive(Debug)]
+    struct CountingLeaf {
+        hash_calls: AtomicUsize,
+        value: u8,
+    }
+
+    impl Resolve for CountingLeaf {
+        type Output<'a>
+            = &'a CountingLeaf
+        where
+            Self: 'a;
+
+        fn resolve(&self, _visitor: &mut impl Visitor) -> ResolveResult<Self::Output<'_>> {
+            Ok(self)
+        }
+    }
+
+    impl HashValue for &CountingLeaf {
+        // TEST FIXTURE ONLY, not a template for a real `HashValue` impl —
+        // see the matching comment in `dependency/mod.rs`'s `Foo` impl.
+        // `lane_1` here is derived from `lane_0`'s output rather than an
+        // independently-seeded second pass, since `Visitor::hasher()` only
+        // hands back one `Hasher`.
+        fn hash_value(&self, hasher: &mut impl Hasher) -> NodeHash {
+            self.hash_calls.fetch_add(1, Ordering::SeqCst);
+            hasher.write_u8(self.value);
+            let lane_0 = hasher.finish();
+            hasher.write_u64(lane_0);
+            hasher.write_u8(!self.value);
+            let lane_1 = hasher.finish();
+            NodeHash::Hashed(Fingerprint::new(lane_0, lane_1))
+        }
+    }
+
+    #[test]
+    fn diamond_is_hashed_exactly_once_per_pass() {
+        let leaf = Arc::new(CountingLeaf {
+            hash_calls: AtomicUsize::new(0),
+            value: 7,
+        });
+        let shared = Arc::new(SyncDependency::new(leaf));
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                let shared = Arc::clone(&shared);
+                scope.spawn(move || {
+                    let mut visitor = HashSetVisitor::new();
+                    shared.resolve(&mut visitor).unwrap();
+                });
+            }
+        });
+
+        assert_eq!(
+            shared.dependency.hash_calls.load(Ordering::SeqCst),
+            1,
+            "8 threads racing to resolve the same shared ancestor should hash it exactly once"
+        );
+
+        // A second pass (after `begin_pass`) hashes again exactly once,
+        // it isn't stuck on the first pass's `Done` forever.
+        shared.begin_pass();
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                let shared = Arc::clone(&shared);
+                scope.spawn(move || {
+                    let mut visitor = HashSetVisitor::new();
+                    shared.resolve(&mut visitor).unwrap();
+                });
+            }
+        });
+        assert_eq!(shared.dependency.hash_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn parallel_resolver_runs_every_job_exactly_once() {
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let resolver = ParallelResolver::new(4);
+        let jobs: Vec<_> = (0..20)
+            .map(|i| {
+                let results = Arc::clone(&results);
+                move || {
+                    results.lock().unwrap().push(i);
+                }
+            })
+            .collect();
+
+        resolver.run(jobs);
+
+        let mut results = results.lock().unwrap().clone();
+        results.sort_unstable();
+        assert_eq!(results, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn run_graph_collapses_a_shared_ancestor_while_resolving_an_independent_branch() {
+        use super::super::dep_graph::SimpleNode;
+
+        // Diamond: "leaf" is shared by "b" and "c", both feeding "root".
+        // "other" is a wholly independent branch with its own leaf.
+        let nodes = vec![
+            SimpleNode::new("leaf", vec![]),
+            SimpleNode::new("b", vec!["leaf"]),
+            SimpleNode::new("c", vec!["leaf"]),
+            SimpleNode::new("root", vec!["b", "c"]),
+            SimpleNode::new("other", vec![]),
+        ];
+
+        let shared = Arc::new(SyncDependency::new(Arc::new(CountingLeaf {
+            hash_calls: AtomicUsize::new(0),
+            value: 1,
+        })));
+        let independent = Arc::new(SyncDependency::new(Arc::new(CountingLeaf {
+            hash_calls: AtomicUsize::new(0),
+            value: 2,
+        })));
+        let processed = Arc::new(Mutex::new(Vec::new()));
+
+        let resolver = ParallelResolver::new(4);
+        resolver.run_graph(&nodes, |id| {
+            let mut visitor = HashSetVisitor::new();
+            match id {
+                "other" => {
+                    independent.resolve(&mut visitor).unwrap();
+                }
+                _ => {
+                    // "leaf", "b", "c", and "root" all touch the same
+                    // shared ancestor, as if each depended on it.
+                    shared.resolve(&mut visitor).unwrap();
+                }
+            }
+            processed.lock().unwrap().push(id);
+        });
+
+        let mut processed = processed.lock().unwrap().clone();
+        processed.sort_unstable();
+        assert_eq!(processed, vec!["b", "c", "leaf", "other", "root"]);
+
+        assert_eq!(
+            shared.dependency.hash_calls.load(Ordering::SeqCst),
+            1,
+            "4 nodes all touching the same shared ancestor should hash it exactly once"
+        );
+        assert_eq!(independent.dependency.hash_calls.load(Ordering::SeqCst), 1);
+    }
+}
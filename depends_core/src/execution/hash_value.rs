@@ -0,0 +1,55 @@
+use std::hash::Hasher;
+
+/// Two 64-bit lanes from hashing the same value with two differently-seeded
+/// [`Hasher`]s, mirroring rustc's `Fingerprint`. Equality is a pairwise
+/// comparison, so callers comparing `NodeHash`es (e.g. `Dependency::resolve`)
+/// need no changes to benefit from the wider hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fingerprint(u64, u64);
+
+impl Fingerprint {
+    pub fn new(lane_0: u64, lane_1: u64) -> Self {
+        Fingerprint(lane_0, lane_1)
+    }
+
+    pub fn as_u128(self) -> u128 {
+        ((self.0 as u128) << 64) | self.1 as u128
+    }
+}
+
+/// The hash state observed for a node's resolved value. `Dependency::resolve`
+/// compares two `NodeHash`es to decide `Clean` vs `Dirty`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeHash {
+    /// No value has been hashed yet.
+    NotHashed,
+    /// A [`Fingerprint`] of the last resolved value, widened from a bare
+    /// `u64` so a single-lane collision can't produce a false `Clean`.
+    Hashed(Fingerprint),
+}
+
+/// Implemented by a node's resolved output so `Dependency` can hash it to
+/// detect changes between resolves.
+pub trait HashValue {
+    fn hash_value(&self, hasher: &mut impl Hasher) -> NodeHash;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_equality_is_pairwise() {
+        assert_eq!(Fingerprint::new(1, 2), Fingerprint::new(1, 2));
+        assert_ne!(Fingerprint::new(1, 2), Fingerprint::new(1, 3));
+        assert_ne!(Fingerprint::new(1, 2), Fingerprint::new(2, 2));
+    }
+
+    #[test]
+    fn as_u128_round_trips_both_lanes() {
+        let fingerprint = Fingerprint::new(0x1122_3344_5566_7788, 0x99AA_BBCC_DDEE_FF00);
+        let raw = fingerprint.as_u128();
+        assert_eq!((raw >> 64) as u64, 0x1122_3344_5566_7788);
+        assert_eq!(raw as u64, 0x99AA_BBCC_DDEE_FF00);
+    }
+}